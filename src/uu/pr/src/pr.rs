@@ -19,7 +19,7 @@ use quick_error::ResultExt;
 use regex::Regex;
 use std::convert::From;
 use std::fs::{metadata, File, Metadata};
-use std::io::{stdin, stdout, BufRead, BufReader, Lines, Read, Stdin, Stdout, Write};
+use std::io::{stdin, stdout, BufRead, BufReader, Read, Stdin, Stdout, Write};
 use std::iter::{FlatMap, Map};
 use std::num::ParseIntError;
 #[cfg(unix)]
@@ -54,6 +54,14 @@ static COLUMN_STRING_SEPARATOR_OPTION: &str = "S";
 static MERGE_FILES_PRINT: &str = "m";
 static OFFSET_SPACES_OPTION: &str = "o";
 static JOIN_LINES_OPTION: &str = "J";
+static WRAP_OPTION: &str = "wrap";
+static WRAP_WIDTH_OPTION: &str = "wrap-width";
+static COLOR_OPTION: &str = "color";
+static EXPAND_TABS_OPTION: &str = "e";
+static OUTPUT_TABS_OPTION: &str = "i";
+static DEFAULT_TAB_GAP: usize = 8;
+static SGR_RESET: &str = "\u{1B}[0m";
+static ESC: char = '\u{1B}';
 static FILE_STDIN: &str = "-";
 static READ_BUFFER_SIZE: usize = 1024 * 64;
 static DEFAULT_COLUMN_WIDTH: usize = 72;
@@ -81,6 +89,14 @@ struct OutputOptions {
     join_lines: bool,
     col_sep_for_printing: String,
     line_width: Option<usize>,
+    wrap_mode: WrapMode,
+    wrap_goal_width: Option<usize>,
+    color_mode: ColorMode,
+    lossy: bool,
+    /// `-e`: expand input tabs of the given char to spaces on the given tab-stop gap.
+    expand_tabs: Option<(char, usize)>,
+    /// `-i`: collapse output space runs ending on the given tab-stop gap to the given char.
+    replace_tabs: Option<(char, usize)>,
 }
 
 struct FileLine {
@@ -88,7 +104,7 @@ struct FileLine {
     line_number: usize,
     page_number: usize,
     group_key: usize,
-    line_content: Result<String, IOError>,
+    line_content: Result<Vec<u8>, IOError>,
     form_feeds_after: usize,
 }
 
@@ -105,6 +121,30 @@ struct ColumnModeOptions {
     across_mode: bool,
 }
 
+/// How over-long input lines are made to fit into a text column.
+///
+/// `Truncate` is the historical behavior (clip at the column width). The two
+/// folding modes re-flow a line on whitespace the way `fmt` reflows a
+/// paragraph, emitting each produced physical line as its own `FileLine`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WrapMode {
+    /// Clip anything that does not fit into the column.
+    Truncate,
+    /// Pack as many words as fit onto each physical row, left to right.
+    Greedy,
+    /// Choose breaks minimizing total line badness (Knuth-Plass).
+    Optimal,
+}
+
+/// What to do with ANSI/SGR escape sequences embedded in the input.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    /// Pass sequences through untouched, counting them as zero display width.
+    Pass,
+    /// Remove every recognized escape sequence from the output.
+    Strip,
+}
+
 impl AsRef<OutputOptions> for OutputOptions {
     fn as_ref(&self) -> &OutputOptions {
         self
@@ -135,7 +175,7 @@ impl Default for FileLine {
             line_number: 0,
             page_number: 0,
             group_key: 0,
-            line_content: Ok(String::new()),
+            line_content: Ok(Vec::new()),
             form_feeds_after: 0,
         }
     }
@@ -378,6 +418,64 @@ pub fn uumain(args: impl uucore::Args) -> i32 {
         Occur::Optional,
     );
 
+    opts.opt(
+        "",
+        WRAP_OPTION,
+        "Re-flow input lines that do not fit into a text column instead of truncating them. \
+         MODE is 'greedy' (pack as many words per row as fit, the default) or 'optimal' \
+         (a Knuth-Plass dynamic program that minimizes total line badness). Ignored when -J is set.",
+        "[MODE]",
+        HasArg::Maybe,
+        Occur::Optional,
+    );
+
+    opts.opt(
+        "",
+        WRAP_WIDTH_OPTION,
+        "Goal width in columns for --wrap. Defaults to the text column width.",
+        "width",
+        HasArg::Yes,
+        Occur::Optional,
+    );
+
+    opts.opt(
+        "",
+        COLOR_OPTION,
+        "How to handle ANSI/SGR escape sequences in the input: 'pass' (the default) \
+         preserves them and counts them as zero width, 'strip' removes them.",
+        "pass|strip",
+        HasArg::Yes,
+        Occur::Optional,
+    );
+
+    opts.opt(
+        EXPAND_TABS_OPTION,
+        "expand-tabs",
+        "Expand input tabs to spaces. The optional char selects the input tab \
+         character (default <tab>) and the optional gap the tab-stop spacing (default 8).",
+        "[char][gap]",
+        HasArg::Maybe,
+        Occur::Optional,
+    );
+
+    opts.opt(
+        OUTPUT_TABS_OPTION,
+        "output-tabs",
+        "Replace runs of output spaces ending on a tab stop with a tab. The optional \
+         char selects the output tab character (default <tab>) and the optional gap the \
+         tab-stop spacing (default 8).",
+        "[char][gap]",
+        HasArg::Maybe,
+        Occur::Optional,
+    );
+
+    opts.optflag(
+        "v",
+        "lossy",
+        "Render non-UTF-8 and control bytes safely: invalid bytes become U+FFFD \
+         and C0 control bytes are shown caret-escaped. By default bytes are passed through untouched.",
+    );
+
     opts.optflag("", "help", "display this help and exit");
     opts.optflag("V", "version", "output version information and exit");
 
@@ -730,6 +828,40 @@ fn build_options(
 
     let across_mode: bool = matches.opt_present(ACROSS_OPTION);
 
+    let wrap_mode: WrapMode = if matches.opt_present(WRAP_OPTION) {
+        match matches.opt_str(WRAP_OPTION).as_deref() {
+            Some("optimal") | Some("knuth-plass") => WrapMode::Optimal,
+            None | Some("") | Some("greedy") => WrapMode::Greedy,
+            Some(other) => {
+                return Err(PrError::EncounteredErrors(format!(
+                    "invalid --{} argument '{}'",
+                    WRAP_OPTION, other
+                )));
+            }
+        }
+    } else {
+        WrapMode::Truncate
+    };
+
+    let wrap_goal_width: Option<usize> = match parse_usize(matches, WRAP_WIDTH_OPTION) {
+        Some(res) => Some(res?),
+        None => None,
+    };
+
+    let expand_tabs: Option<(char, usize)> = parse_tab_option(matches, EXPAND_TABS_OPTION)?;
+    let replace_tabs: Option<(char, usize)> = parse_tab_option(matches, OUTPUT_TABS_OPTION)?;
+
+    let color_mode: ColorMode = match matches.opt_str(COLOR_OPTION).as_deref() {
+        None | Some("pass") | Some("raw") => ColorMode::Pass,
+        Some("strip") => ColorMode::Strip,
+        Some(other) => {
+            return Err(PrError::EncounteredErrors(format!(
+                "invalid --{} argument '{}'",
+                COLOR_OPTION, other
+            )));
+        }
+    };
+
     let column_separator: String = match matches.opt_str(COLUMN_STRING_SEPARATOR_OPTION) {
         Some(x) => Some(x),
         None => matches.opt_str(COLUMN_CHAR_SEPARATOR_OPTION),
@@ -833,9 +965,50 @@ fn build_options(
         join_lines,
         col_sep_for_printing,
         line_width,
+        wrap_mode,
+        wrap_goal_width,
+        color_mode,
+        lossy: matches.opt_present("lossy"),
+        expand_tabs,
+        replace_tabs,
     })
 }
 
+/// Parses the `-e`/`-i` `[char][gap]` argument: an optional alternate tab
+/// character (any leading nondigit) followed by an optional tab-stop gap
+/// (default 8). Returns `None` when the option is absent.
+fn parse_tab_option(matches: &Matches, opt: &str) -> Result<Option<(char, usize)>, PrError> {
+    if !matches.opt_present(opt) {
+        return Ok(None);
+    }
+
+    let mut tab_char: char = TAB;
+    let mut gap: usize = DEFAULT_TAB_GAP;
+
+    if let Some(spec) = matches.opt_str(opt) {
+        let mut rest: &str = &spec;
+        if let Some(first) = rest.chars().next() {
+            if !first.is_ascii_digit() {
+                tab_char = first;
+                rest = &rest[first.len_utf8()..];
+            }
+        }
+        if !rest.is_empty() {
+            gap = rest.parse::<usize>().map_err(|_e| {
+                PrError::EncounteredErrors(format!("invalid -{} argument '{}'", opt, spec))
+            })?;
+        }
+        if gap == 0 {
+            return Err(PrError::EncounteredErrors(format!(
+                "invalid -{} argument '{}'",
+                opt, spec
+            )));
+        }
+    }
+
+    Ok(Some((tab_char, gap)))
+}
+
 fn open(path: &str) -> Result<Box<dyn Read>, PrError> {
     if path == FILE_STDIN {
         let stdin: Stdin = stdin();
@@ -864,32 +1037,54 @@ fn open(path: &str) -> Result<Box<dyn Read>, PrError> {
         .unwrap_or(Err(PrError::NotExists(path.to_string())))
 }
 
-fn split_lines_if_form_feed(file_content: Result<String, IOError>) -> Vec<FileLine> {
+/// Reads raw byte lines from `reader`, splitting on the `\n` byte and stripping
+/// a trailing `\r\n`/`\n` the way `BufRead::lines` does, but keeping payloads as
+/// `Vec<u8>` so invalid UTF-8 or binary input never aborts the program.
+fn read_lines(reader: Box<dyn Read>) -> Box<dyn Iterator<Item = Result<Vec<u8>, IOError>>> {
+    let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, reader);
+    Box::new(std::iter::from_fn(move || {
+        let mut buf: Vec<u8> = Vec::new();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => None,
+            Ok(_) => {
+                if buf.last() == Some(&b'\n') {
+                    buf.pop();
+                    if buf.last() == Some(&b'\r') {
+                        buf.pop();
+                    }
+                }
+                Some(Ok(buf))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }))
+}
+
+fn split_lines_if_form_feed(file_content: Result<Vec<u8>, IOError>) -> Vec<FileLine> {
     file_content
         .map(|content| {
             let mut lines: Vec<FileLine> = Vec::new();
             let mut f_occurred: usize = 0;
             let mut chunk: Vec<u8> = Vec::new();
-            for byte in content.as_bytes() {
-                if byte == &FF {
+            for byte in content {
+                if byte == FF {
                     f_occurred += 1;
                 } else {
                     if f_occurred != 0 {
                         // First time byte occurred in the scan
                         lines.push(FileLine {
-                            line_content: Ok(String::from_utf8(chunk.clone()).unwrap()),
+                            line_content: Ok(std::mem::take(&mut chunk)),
                             form_feeds_after: f_occurred,
                             ..FileLine::default()
                         });
-                        chunk.clear();
                     }
-                    chunk.push(*byte);
+                    chunk.push(byte);
                     f_occurred = 0;
                 }
             }
 
             lines.push(FileLine {
-                line_content: Ok(String::from_utf8(chunk).unwrap()),
+                line_content: Ok(chunk),
                 form_feeds_after: f_occurred,
                 ..FileLine::default()
             });
@@ -904,9 +1099,215 @@ fn split_lines_if_form_feed(file_content: Result<String, IOError>) -> Vec<FileLi
         })
 }
 
+/// Returns the goal width used when folding over-long lines, or `None` when no
+/// wrapping should happen (truncate mode, `-J`, or no column width to target).
+///
+/// The goal defaults to the *per-text-column* width that each cell is
+/// truncated to downstream — `(line_width - (columns - 1)) / columns` — not the
+/// whole page width, so wrapping actually fits the column in multi-column mode.
+/// It can be overridden with `--wrap-width`. Room for the line-number prefix is
+/// reserved up front so the produced physical lines still fit once numbering is
+/// added downstream.
+fn wrap_target_width(options: &OutputOptions) -> Option<usize> {
+    if options.join_lines || options.wrap_mode == WrapMode::Truncate {
+        return None;
+    }
+    let base = match options.wrap_goal_width {
+        Some(width) => width,
+        None => {
+            let line_width = options.line_width?;
+            let columns = options.merge_files_print.unwrap_or(get_columns(options));
+            (line_width - (columns - 1)) / columns
+        }
+    };
+    let reserved = options
+        .number
+        .as_ref()
+        .map(|i| i.width + i.separator.chars().map(char_display_width).sum::<usize>())
+        .unwrap_or(0);
+    Some(base.saturating_sub(reserved).max(1))
+}
+
+/// Folds a single input line into one or more physical lines that fit the goal
+/// width, returning each as its own `FileLine` so that pagination, numbering,
+/// and column balancing keep operating on physical lines. Any trailing form
+/// feeds stay attached to the last produced line.
+///
+/// Widths here are counted in characters, not display cells: a wide character
+/// or a `<tab>` counts as one, so a produced line holding them can still be
+/// wider than the goal in cells and is then truncated downstream.
+fn reflow_file_line(
+    file_line: FileLine,
+    mode: WrapMode,
+    goal: Option<usize>,
+    lossy: bool,
+) -> Vec<FileLine> {
+    let goal: usize = match goal {
+        Some(goal) => goal,
+        None => return vec![file_line],
+    };
+
+    // Word splitting needs text. Valid UTF-8 lines reflow as-is; non-UTF-8
+    // lines are only decoded (lossily) under --lossy, otherwise they are
+    // passed through untouched so byte-preservation is honored by default.
+    let content: String = match &file_line.line_content {
+        Ok(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) => {
+                if text.chars().count() <= goal {
+                    return vec![file_line];
+                }
+                text.to_owned()
+            }
+            Err(_) if lossy => {
+                let text = String::from_utf8_lossy(bytes);
+                if text.chars().count() <= goal {
+                    return vec![file_line];
+                }
+                text.into_owned()
+            }
+            Err(_) => return vec![file_line],
+        },
+        Err(_) => return vec![file_line],
+    };
+
+    let rows: Vec<String> = match mode {
+        WrapMode::Greedy => greedy_wrap(&content, goal),
+        WrapMode::Optimal => optimal_wrap(&content, goal),
+        WrapMode::Truncate => return vec![file_line],
+    };
+
+    let FileLine {
+        file_id,
+        form_feeds_after,
+        ..
+    } = file_line;
+    let last: usize = rows.len().saturating_sub(1);
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| FileLine {
+            file_id,
+            line_content: Ok(row.into_bytes()),
+            form_feeds_after: if i == last { form_feeds_after } else { 0 },
+            ..FileLine::default()
+        })
+        .collect()
+}
+
+/// Breaks a single word that is wider than `goal` into `goal`-sized pieces so it
+/// can be placed without overflowing the column. Narrow words are returned as-is.
+fn force_break(word: &str, goal: usize) -> Vec<String> {
+    if goal == 0 || word.chars().count() <= goal {
+        return vec![word.to_string()];
+    }
+    let mut pieces: Vec<String> = Vec::new();
+    let mut piece: String = String::new();
+    let mut count: usize = 0;
+    for ch in word.chars() {
+        piece.push(ch);
+        count += 1;
+        if count == goal {
+            pieces.push(std::mem::take(&mut piece));
+            count = 0;
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Greedy line breaker: packs as many whitespace-separated words as fit per
+/// physical row, force-breaking any single word wider than the column.
+fn greedy_wrap(line: &str, goal: usize) -> Vec<String> {
+    let mut rows: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    for word in line.split_whitespace() {
+        for piece in force_break(word, goal) {
+            let piece_len: usize = piece.chars().count();
+            if current.is_empty() {
+                current = piece;
+            } else if current.chars().count() + 1 + piece_len <= goal {
+                current.push(' ');
+                current.push_str(&piece);
+            } else {
+                rows.push(std::mem::take(&mut current));
+                current = piece;
+            }
+        }
+    }
+    if !current.is_empty() || rows.is_empty() {
+        rows.push(current);
+    }
+    rows
+}
+
+/// Optimal line breaker using the Knuth-Plass dynamic program described in the
+/// `pr` wrap request: `cost[0] = 0` and `cost[i] = min over j < i of
+/// cost[j] + badness(words[j..i])`, where badness is the squared slack to the
+/// goal width when the packed line fits and effectively infinite when it
+/// overflows, so overflow is only ever chosen for a single word that has
+/// already been force-broken as small as possible.
+fn optimal_wrap(line: &str, goal: usize) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in line.split_whitespace() {
+        words.extend(force_break(word, goal));
+    }
+    if words.is_empty() {
+        return vec![String::new()];
+    }
+
+    let lens: Vec<usize> = words.iter().map(|w| w.chars().count()).collect();
+    let n: usize = words.len();
+    let inf: usize = usize::MAX;
+    let mut cost: Vec<usize> = vec![inf; n + 1];
+    let mut pred: Vec<usize> = vec![0; n + 1];
+    cost[0] = 0;
+
+    for i in 1..=n {
+        let mut line_len: usize = 0;
+        for j in (0..i).rev() {
+            if j != i - 1 {
+                // space separating this word from the following one
+                line_len += 1;
+            }
+            line_len += lens[j];
+
+            let badness: usize = if line_len <= goal {
+                let slack = goal - line_len;
+                slack * slack
+            } else if j == i - 1 {
+                // A lone word still wider than the column; it must be placed
+                // anyway, but penalize the overflow.
+                let over = line_len - goal;
+                over * over
+            } else {
+                // Adding earlier words only widens the line further.
+                break;
+            };
+
+            if cost[j] != inf && cost[j] + badness < cost[i] {
+                cost[i] = cost[j] + badness;
+                pred[i] = j;
+            }
+        }
+    }
+
+    let mut bounds: Vec<(usize, usize)> = Vec::new();
+    let mut i: usize = n;
+    while i > 0 {
+        let j = pred[i];
+        bounds.push((j, i));
+        i = j;
+    }
+    bounds.reverse();
+    bounds
+        .into_iter()
+        .map(|(j, i)| words[j..i].join(" "))
+        .collect()
+}
+
 fn pr(path: &str, options: &OutputOptions) -> Result<i32, PrError> {
-    let lines: Lines<BufReader<Box<dyn Read>>> =
-        BufReader::with_capacity(READ_BUFFER_SIZE, open(path)?).lines();
+    let lines = read_lines(open(path)?);
 
     let pages: Box<dyn Iterator<Item = (usize, Vec<FileLine>)>> =
         read_stream_and_create_pages(options, lines, 0);
@@ -922,18 +1323,24 @@ fn pr(path: &str, options: &OutputOptions) -> Result<i32, PrError> {
 
 fn read_stream_and_create_pages(
     options: &OutputOptions,
-    lines: Lines<BufReader<Box<dyn Read>>>,
+    lines: Box<dyn Iterator<Item = Result<Vec<u8>, IOError>>>,
     file_id: usize,
 ) -> Box<dyn Iterator<Item = (usize, Vec<FileLine>)>> {
     let start_page: usize = options.start_page;
     let start_line_number: usize = get_start_line_number(options);
     let last_page: Option<usize> = options.end_page;
     let lines_needed_per_page: usize = lines_to_read_for_page(options);
+    let wrap_mode: WrapMode = options.wrap_mode;
+    let wrap_goal: Option<usize> = wrap_target_width(options);
+    let lossy: bool = options.lossy;
 
     Box::new(
         lines
             .map(split_lines_if_form_feed)
             .flatten()
+            .flat_map(move |file_line: FileLine| {
+                reflow_file_line(file_line, wrap_mode, wrap_goal, lossy)
+            })
             .enumerate()
             .map(move |i: (usize, FileLine)| FileLine {
                 line_number: i.0 + start_line_number,
@@ -1001,8 +1408,7 @@ fn mpr(paths: &Vec<String>, options: &OutputOptions) -> Result<i32, PrError> {
         .iter()
         .enumerate()
         .map(|indexed_path: (usize, &String)| {
-            let lines =
-                BufReader::with_capacity(READ_BUFFER_SIZE, open(indexed_path.1).unwrap()).lines();
+            let lines = read_lines(open(indexed_path.1).unwrap());
 
             read_stream_and_create_pages(options, lines, indexed_path.0)
                 .map(move |x: (usize, Vec<FileLine>)| {
@@ -1151,25 +1557,30 @@ fn write_columns(
     let blank_line: FileLine = FileLine::default();
     for row in table {
         let indexes = row.len();
+        // Assemble the whole output row first so -i tab compression sees the
+        // true line column of each cell rather than restarting at zero.
+        let mut row_buf: Vec<u8> = Vec::new();
         for (i, cell) in row.iter().enumerate() {
             if cell.is_none() && options.merge_files_print.is_some() {
-                out.write_all(
-                    get_line_for_printing(&options, &blank_line, columns, i, &line_width, indexes)
-                        .as_bytes(),
-                )?;
+                row_buf.extend_from_slice(&get_line_for_printing(
+                    &options, &blank_line, columns, i, &line_width, indexes,
+                ));
             } else if cell.is_none() {
                 not_found_break = true;
                 break;
             } else if cell.is_some() {
                 let file_line: &FileLine = cell.unwrap();
 
-                out.write_all(
-                    get_line_for_printing(&options, file_line, columns, i, &line_width, indexes)
-                        .as_bytes(),
-                )?;
+                row_buf.extend_from_slice(&get_line_for_printing(
+                    &options, file_line, columns, i, &line_width, indexes,
+                ));
                 lines_printed += 1;
             }
         }
+        if let Some((tab_char, gap)) = options.replace_tabs {
+            row_buf = compress_output_tabs(&row_buf, tab_char, gap);
+        }
+        out.write_all(&row_buf)?;
         if not_found_break && feed_line_present {
             break;
         } else {
@@ -1187,45 +1598,299 @@ fn get_line_for_printing(
     index: usize,
     line_width: &Option<usize>,
     indexes: usize,
-) -> String {
+) -> Vec<u8> {
     // Check this condition
     let blank_line = String::new();
     let fmtd_line_number: String = get_fmtd_line_number(&options, file_line.line_number, index);
 
-    let mut complete_line = format!(
-        "{}{}",
-        fmtd_line_number,
-        file_line.line_content.as_ref().unwrap()
-    );
+    let content: &[u8] = match file_line.line_content.as_ref() {
+        Ok(bytes) => bytes,
+        Err(_) => &[],
+    };
+    let mut complete_line: Vec<u8> = Vec::with_capacity(fmtd_line_number.len() + content.len());
+    complete_line.extend_from_slice(fmtd_line_number.as_bytes());
+    complete_line.extend_from_slice(content);
+
+    // -e: expand input tabs before any width math so truncation uses the
+    // post-expansion column positions.
+    if let Some((tab_char, gap)) = options.expand_tabs {
+        complete_line = expand_input_tabs(&complete_line, tab_char, gap);
+    }
 
     let offset_spaces: &String = &options.offset_spaces;
 
-    let tab_count: usize = complete_line.chars().filter(|i| i == &TAB).count();
-
-    let display_length = complete_line.len() + (tab_count * 7);
-
     let sep = if (index + 1) != indexes && !options.join_lines {
         &options.col_sep_for_printing
     } else {
         &blank_line
     };
 
-    format!(
-        "{}{}{}",
-        offset_spaces,
-        line_width
-            .map(|i| {
-                let min_width = (i - (columns - 1)) / columns;
-                if display_length < min_width {
-                    for _i in 0..(min_width - display_length) {
-                        complete_line.push(' ');
+    let min_width: Option<usize> = line_width.map(|i| (i - (columns - 1)) / columns);
+    let rendered = render_cell(&complete_line, min_width, options.color_mode, options.lossy);
+
+    // -i output tab compression is applied once to the whole assembled row in
+    // write_columns so tab stops are computed from the true line column.
+    let mut out: Vec<u8> = Vec::with_capacity(offset_spaces.len() + rendered.len() + sep.len());
+    out.extend_from_slice(offset_spaces.as_bytes());
+    out.extend_from_slice(&rendered);
+    out.extend_from_slice(sep.as_bytes());
+    out
+}
+
+/// Expands occurrences of `tab_char` to spaces, advancing to the next multiple
+/// of `gap` from the current display column. Other bytes are passed through and
+/// advance the column by their display width (invalid bytes count as one cell).
+fn expand_input_tabs(bytes: &[u8], tab_char: char, gap: usize) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut col = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let (ch, len) = decode_char(&bytes[i..]);
+        match ch {
+            Some(c) if c == tab_char => {
+                let next = ((col / gap) + 1) * gap;
+                for _ in col..next {
+                    out.push(b' ');
+                }
+                col = next;
+            }
+            Some(c) => {
+                out.extend_from_slice(&bytes[i..i + len]);
+                col += char_display_width(c);
+            }
+            None => {
+                out.push(bytes[i]);
+                col += 1;
+            }
+        }
+        i += len;
+    }
+    out
+}
+
+/// Collapses runs of output spaces into `tab_char` wherever a run reaches a
+/// multiple of `gap`, preserving the visual column layout. Spaces that cannot
+/// reach the next tab stop are emitted literally.
+fn compress_output_tabs(bytes: &[u8], tab_char: char, gap: usize) -> Vec<u8> {
+    let tab_bytes = tab_char.to_string().into_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut col = 0;
+    let mut spaces = 0;
+    let mut i = 0;
+
+    let flush = |out: &mut Vec<u8>, col: usize, spaces: &mut usize| {
+        let mut start = col - *spaces;
+        while *spaces > 0 {
+            let next = ((start / gap) + 1) * gap;
+            let to_stop = next - start;
+            if to_stop <= *spaces {
+                out.extend_from_slice(&tab_bytes);
+                *spaces -= to_stop;
+                start = next;
+            } else {
+                for _ in 0..*spaces {
+                    out.push(b' ');
+                }
+                *spaces = 0;
+            }
+        }
+    };
+
+    while i < bytes.len() {
+        let (ch, len) = decode_char(&bytes[i..]);
+        match ch {
+            Some(' ') => {
+                spaces += 1;
+                col += 1;
+            }
+            Some(c) => {
+                flush(&mut out, col, &mut spaces);
+                out.extend_from_slice(&bytes[i..i + len]);
+                col += char_display_width(c);
+            }
+            None => {
+                flush(&mut out, col, &mut spaces);
+                out.push(bytes[i]);
+                col += 1;
+            }
+        }
+        i += len;
+    }
+    flush(&mut out, col, &mut spaces);
+    out
+}
+
+/// Returns the number of terminal cells a character occupies: 0 for
+/// zero-width and combining marks, 2 for East-Asian wide and fullwidth code
+/// points, and 1 otherwise. A `<tab>` keeps its historical eight-cell width.
+fn char_display_width(c: char) -> usize {
+    if c == TAB {
+        return 8;
+    }
+    let cp = c as u32;
+    if cp == 0 || is_zero_width(cp) {
+        0
+    } else if is_wide(cp) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Renders a single column cell: accounts for character display width, treats
+/// ANSI CSI escape sequences as zero width (passing them through or stripping
+/// them per `color`), truncates to `min_width` cells without cutting inside a
+/// wide character or an escape sequence, and pads the remainder with spaces.
+///
+/// When a column is truncated while an SGR style is still open, a trailing
+/// reset (`ESC[0m`) is emitted so color does not bleed into the separator or
+/// the next cell. CSI sequences are `ESC [`, then parameter/intermediate bytes,
+/// terminated by a byte in `0x40..=0x7E`, matching how a VT100 parser separates
+/// control sequences from printable cells.
+///
+/// The cell is built as raw bytes: valid UTF-8 characters are passed through
+/// with their display width, and invalid bytes count as one cell each — passed
+/// through untouched by default, or substituted with U+FFFD under `lossy`
+/// (which also caret-escapes C0 control bytes).
+fn render_cell(s: &[u8], min_width: Option<usize>, color: ColorMode, lossy: bool) -> Vec<u8> {
+    let esc = ESC as u8;
+    let mut out: Vec<u8> = Vec::new();
+    let mut used = 0;
+    let mut i = 0;
+    let mut style_open = false;
+    let mut truncated = false;
+
+    while i < s.len() {
+        if s[i] == esc && s.get(i + 1) == Some(&b'[') {
+            // Consume a CSI sequence up to and including its final byte.
+            let mut j = i + 2;
+            while j < s.len() && !(0x40..=0x7E).contains(&s[j]) {
+                j += 1;
+            }
+            let final_byte = s.get(j).copied();
+            let end = if j < s.len() { j + 1 } else { j };
+            if color == ColorMode::Pass {
+                out.extend_from_slice(&s[i..end]);
+                if final_byte == Some(b'm') {
+                    // SGR: a bare or zero parameter resets, anything else opens a style.
+                    let params = &s[i + 2..j];
+                    style_open = !(params.is_empty() || params == b"0");
+                }
+            }
+            i = end;
+            continue;
+        }
+
+        let (ch, len) = decode_char(&s[i..]);
+        match ch {
+            // C0 control byte shown caret-escaped when rendering lossily.
+            Some(c) if lossy && (c as u32) < 0x20 && c != TAB => {
+                if let Some(mw) = min_width {
+                    if used + 2 > mw {
+                        truncated = true;
+                        break;
+                    }
+                }
+                out.push(b'^');
+                out.push(((c as u8) ^ 0x40) & 0x7F);
+                used += 2;
+                i += len;
+            }
+            Some(c) => {
+                let w = char_display_width(c);
+                if let Some(mw) = min_width {
+                    if used + w > mw {
+                        truncated = true;
+                        break;
+                    }
+                }
+                out.extend_from_slice(&s[i..i + len]);
+                used += w;
+                i += len;
+            }
+            None => {
+                // Invalid byte: one cell, passed through or replaced.
+                if let Some(mw) = min_width {
+                    if used + 1 > mw {
+                        truncated = true;
+                        break;
                     }
                 }
+                if lossy {
+                    out.extend_from_slice("\u{FFFD}".as_bytes());
+                } else {
+                    out.push(s[i]);
+                }
+                used += 1;
+                i += 1;
+            }
+        }
+    }
 
-                complete_line.chars().take(min_width).collect()
-            })
-            .unwrap_or(complete_line),
-        sep
+    if truncated && style_open && color == ColorMode::Pass {
+        out.extend_from_slice(SGR_RESET.as_bytes());
+    }
+
+    if let Some(mw) = min_width {
+        for _ in used..mw {
+            out.push(b' ');
+        }
+    }
+
+    out
+}
+
+/// Decodes the first UTF-8 character at the start of `bytes`, returning the
+/// character and its byte length, or `(None, 1)` for an invalid leading byte.
+fn decode_char(bytes: &[u8]) -> (Option<char>, usize) {
+    let max = bytes.len().min(4);
+    for len in 1..=max {
+        if let Ok(s) = std::str::from_utf8(&bytes[..len]) {
+            if let Some(c) = s.chars().next() {
+                return (Some(c), len);
+            }
+        }
+    }
+    (None, 1)
+}
+
+/// Zero-width code points: combining marks and explicitly zero-width spaces.
+fn is_zero_width(cp: u32) -> bool {
+    matches!(cp,
+        0x0300..=0x036F // combining diacritical marks
+        | 0x0483..=0x0489
+        | 0x0591..=0x05BD
+        | 0x0610..=0x061A
+        | 0x064B..=0x065F
+        | 0x0670
+        | 0x06D6..=0x06DC
+        | 0x0E31 | 0x0E34..=0x0E3A
+        | 0x1AB0..=0x1AFF // combining diacritical marks extended
+        | 0x1DC0..=0x1DFF // combining diacritical marks supplement
+        | 0x200B..=0x200F // zero-width space / joiners / marks
+        | 0x20D0..=0x20FF // combining marks for symbols
+        | 0xFE20..=0xFE2F // combining half marks
+        | 0xFEFF          // zero-width no-break space
+    )
+}
+
+/// East-Asian wide and fullwidth code points, which occupy two cells.
+fn is_wide(cp: u32) -> bool {
+    matches!(cp,
+        0x1100..=0x115F // Hangul Jamo
+        | 0x2E80..=0x303E // CJK radicals, Kangxi, punctuation
+        | 0x3041..=0x33FF // Hiragana, Katakana, CJK symbols
+        | 0x3400..=0x4DBF // CJK extension A
+        | 0x4E00..=0x9FFF // CJK unified ideographs
+        | 0xA000..=0xA4CF // Yi
+        | 0xAC00..=0xD7A3 // Hangul syllables
+        | 0xF900..=0xFAFF // CJK compatibility ideographs
+        | 0xFE10..=0xFE19 // vertical forms
+        | 0xFE30..=0xFE6F // CJK compatibility forms
+        | 0xFF00..=0xFF60 // fullwidth forms
+        | 0xFFE0..=0xFFE6 // fullwidth signs
+        | 0x1F300..=0x1FAFF // emoji and pictographs
+        | 0x20000..=0x3FFFD // CJK extensions B and beyond
     )
 }
 